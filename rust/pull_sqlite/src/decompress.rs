@@ -0,0 +1,53 @@
+//! Transparent decompression for compressed Parquet inputs.
+//!
+//! `list_parquet_files` already discovers `.parquet.zst` objects alongside
+//! plain `.parquet` ones, but Parquet has no notion of an outer compression
+//! wrapper. Before a compressed object can be parsed, its bytes have to be
+//! decompressed in full first; this happens straight into memory so no
+//! plaintext copy ever touches disk.
+
+use bytes::Bytes;
+use flate2::read::GzDecoder;
+use std::io::{self, Cursor, Read};
+
+/// The compression wrapping detected around a Parquet object, based on its
+/// key suffix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Zstd,
+    Gzip,
+}
+
+impl Compression {
+    /// Infers the compression scheme from an object key's suffix.
+    pub fn from_key(key: &str) -> Self {
+        if key.ends_with(".zst") {
+            Compression::Zstd
+        } else if key.ends_with(".gz") {
+            Compression::Gzip
+        } else {
+            Compression::None
+        }
+    }
+}
+
+/// Decompresses `data` according to `compression`, fully into memory.
+/// Returns `data` unchanged when `compression` is [`Compression::None`].
+pub fn decompress(data: Vec<u8>, compression: Compression) -> io::Result<Bytes> {
+    match compression {
+        Compression::None => Ok(Bytes::from(data)),
+        Compression::Zstd => {
+            let mut decoder = zstd::stream::Decoder::new(Cursor::new(data))?;
+            let mut out = Vec::new();
+            decoder.read_to_end(&mut out)?;
+            Ok(Bytes::from(out))
+        }
+        Compression::Gzip => {
+            let mut decoder = GzDecoder::new(Cursor::new(data));
+            let mut out = Vec::new();
+            decoder.read_to_end(&mut out)?;
+            Ok(Bytes::from(out))
+        }
+    }
+}
@@ -0,0 +1,354 @@
+//! Glicko-2 rating engine.
+//!
+//! Converts ingested [`GameRecord`]s into per-player rating updates, one
+//! [`RatingPeriod`] at a time. Periods are bucketed by `(month, time_control)`
+//! and must be processed in chronological order per `time_control` so that
+//! each player's `(r, rd, sigma)` carries forward correctly; see
+//! [`process_all_periods`].
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Glicko scale factor used to convert between the public rating scale
+/// (`r` around 1500) and the internal Glicko-2 scale (`mu` around 0).
+const SCALE: f64 = 173.7178;
+
+/// System constant constraining how much volatility can change between
+/// rating periods. Smaller values make volatility more stable over time.
+const TAU: f64 = 0.5;
+
+/// Convergence tolerance for the volatility-solving iteration.
+const CONVERGENCE_TOLERANCE: f64 = 1e-6;
+
+/// A single ingested game between two players.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GameRecord {
+    pub player1: String,
+    pub player2: String,
+    pub outcome: f32,
+    pub month: String,
+    pub time_control: String,
+}
+
+/// A player's Glicko-2 rating, expressed on the public scale.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlayerRating {
+    pub r: f64,
+    pub rd: f64,
+    pub sigma: f64,
+}
+
+impl Default for PlayerRating {
+    /// The standard Glicko-2 starting rating for a player with no history.
+    fn default() -> Self {
+        PlayerRating {
+            r: 1500.0,
+            rd: 350.0,
+            sigma: 0.06,
+        }
+    }
+}
+
+impl PlayerRating {
+    fn to_internal(self) -> (f64, f64) {
+        ((self.r - 1500.0) / SCALE, self.rd / SCALE)
+    }
+
+    fn from_internal(mu: f64, phi: f64, sigma: f64) -> Self {
+        PlayerRating {
+            r: SCALE * mu + 1500.0,
+            rd: SCALE * phi,
+            sigma,
+        }
+    }
+}
+
+/// An opponent faced during a rating period, already converted to the
+/// internal scale.
+struct Opponent {
+    mu: f64,
+    phi: f64,
+    score: f64,
+}
+
+fn g(phi: f64) -> f64 {
+    1.0 / (1.0 + 3.0 * phi.powi(2) / std::f64::consts::PI.powi(2)).sqrt()
+}
+
+fn expected_score(mu: f64, mu_j: f64, phi_j: f64) -> f64 {
+    1.0 / (1.0 + (-g(phi_j) * (mu - mu_j)).exp())
+}
+
+/// Runs one Glicko-2 update for a single player given their pre-period
+/// rating and the opponents they faced this period. An empty opponent list
+/// only widens `phi` to reflect the extra period of inactivity.
+fn update_rating(rating: PlayerRating, opponents: &[Opponent]) -> PlayerRating {
+    let (mu, phi) = rating.to_internal();
+    let sigma = rating.sigma;
+
+    if opponents.is_empty() {
+        let phi_star = (phi.powi(2) + sigma.powi(2)).sqrt();
+        return PlayerRating::from_internal(mu, phi_star, sigma);
+    }
+
+    let v_inv: f64 = opponents
+        .iter()
+        .map(|o| {
+            let e = expected_score(mu, o.mu, o.phi);
+            g(o.phi).powi(2) * e * (1.0 - e)
+        })
+        .sum();
+    let v = 1.0 / v_inv;
+
+    let score_sum: f64 = opponents
+        .iter()
+        .map(|o| g(o.phi) * (o.score - expected_score(mu, o.mu, o.phi)))
+        .sum();
+    let delta = v * score_sum;
+
+    let sigma_prime = solve_volatility(delta, phi, v, sigma);
+
+    let phi_star = (phi.powi(2) + sigma_prime.powi(2)).sqrt();
+    let phi_prime = 1.0 / (1.0 / phi_star.powi(2) + v_inv).sqrt();
+    let mu_prime = mu + phi_prime.powi(2) * score_sum;
+
+    PlayerRating::from_internal(mu_prime, phi_prime, sigma_prime)
+}
+
+/// Solves for the new volatility `sigma'` using the Illinois algorithm
+/// (a regula-falsi variant) on Glicko-2's `f(x)`.
+fn solve_volatility(delta: f64, phi: f64, v: f64, sigma: f64) -> f64 {
+    let a = (sigma.powi(2)).ln();
+    let f = |x: f64| {
+        let ex = x.exp();
+        (ex * (delta.powi(2) - phi.powi(2) - v - ex)) / (2.0 * (phi.powi(2) + v + ex).powi(2))
+            - (x - a) / TAU.powi(2)
+    };
+
+    let mut lo = a;
+    let mut hi = if delta.powi(2) > phi.powi(2) + v {
+        (delta.powi(2) - phi.powi(2) - v).ln()
+    } else {
+        let mut k = 1.0;
+        while f(a - k * TAU) < 0.0 {
+            k += 1.0;
+        }
+        a - k * TAU
+    };
+
+    let mut f_lo = f(lo);
+    let mut f_hi = f(hi);
+
+    while (hi - lo).abs() > CONVERGENCE_TOLERANCE {
+        let mid = lo + (lo - hi) * f_lo / (f_hi - f_lo);
+        let f_mid = f(mid);
+
+        if f_mid * f_hi < 0.0 {
+            lo = hi;
+            f_lo = f_hi;
+        } else {
+            f_lo /= 2.0;
+        }
+        hi = mid;
+        f_hi = f_mid;
+    }
+
+    (lo / 2.0).exp()
+}
+
+/// Processes every game for one `(month, time_control)` bucket, updating
+/// `ratings` in place. Players who appear in `games` are updated from their
+/// pre-period rating in `ratings` (or the default rating, if first seen);
+/// players already in `ratings` who sat out this period have only their
+/// deviation widened.
+pub struct RatingPeriod {
+    pub month: String,
+    pub time_control: String,
+}
+
+impl RatingPeriod {
+    pub fn new(month: impl Into<String>, time_control: impl Into<String>) -> Self {
+        RatingPeriod {
+            month: month.into(),
+            time_control: time_control.into(),
+        }
+    }
+
+    /// Applies `games` to `ratings` in place, returning how many games each
+    /// updated player played this period (players who only had their
+    /// deviation widened are not included).
+    pub fn process(&self, games: &[GameRecord], ratings: &mut HashMap<String, PlayerRating>) -> HashMap<String, usize> {
+        let pre_period = ratings.clone();
+        let mut opponents: HashMap<String, Vec<Opponent>> = HashMap::new();
+
+        for game in games {
+            let r1 = pre_period.get(&game.player1).copied().unwrap_or_default();
+            let r2 = pre_period.get(&game.player2).copied().unwrap_or_default();
+            let (mu1, phi1) = r1.to_internal();
+            let (mu2, phi2) = r2.to_internal();
+            let score = game.outcome as f64;
+
+            opponents.entry(game.player1.clone()).or_default().push(Opponent {
+                mu: mu2,
+                phi: phi2,
+                score,
+            });
+            opponents.entry(game.player2.clone()).or_default().push(Opponent {
+                mu: mu1,
+                phi: phi1,
+                score: 1.0 - score,
+            });
+        }
+
+        let mut games_played = HashMap::with_capacity(opponents.len());
+
+        for (player, player_opponents) in &opponents {
+            let rating = pre_period.get(player).copied().unwrap_or_default();
+            ratings.insert(player.clone(), update_rating(rating, player_opponents));
+            games_played.insert(player.clone(), player_opponents.len());
+        }
+
+        for (player, rating) in &pre_period {
+            if !opponents.contains_key(player) {
+                ratings.insert(player.clone(), update_rating(*rating, &[]));
+            }
+        }
+
+        games_played
+    }
+}
+
+/// The state of every player's rating after one `(month, time_control)`
+/// rating period has been processed, plus how many games each player
+/// played during that period.
+pub struct RatingSnapshot {
+    pub month: String,
+    pub time_control: String,
+    pub ratings: HashMap<String, PlayerRating>,
+    pub games_played: HashMap<String, usize>,
+}
+
+/// Carried-forward rating-engine state for one time control between runs:
+/// the last month it processed, and every player's rating as of the end of
+/// that month. Persisting this (rather than the raw games that produced
+/// it) is what lets [`process_all_periods`] resume from a prior run
+/// without holding the entire game history in memory.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TimeControlCheckpoint {
+    pub last_month: Option<String>,
+    pub ratings: HashMap<String, PlayerRating>,
+}
+
+/// Groups `games` by `time_control`, then replays each time control's
+/// months in chronological order, carrying ratings forward across months.
+/// Each time control resumes from its entry in `checkpoints` (or from
+/// scratch, if absent); only months strictly after that checkpoint's
+/// `last_month` are processed. Callers must therefore only pass
+/// newly-ingested games, not the full history; games for the checkpoint's
+/// `last_month` or any earlier month are skipped.
+///
+/// Returns one [`RatingSnapshot`] per newly-processed `(month,
+/// time_control)` bucket, in chronological order within each time control,
+/// plus the updated checkpoint for every time control touched this call —
+/// callers should merge these into their persisted checkpoints for next
+/// time.
+pub fn process_all_periods(
+    games: &[GameRecord],
+    checkpoints: &HashMap<String, TimeControlCheckpoint>,
+) -> (Vec<RatingSnapshot>, HashMap<String, TimeControlCheckpoint>) {
+    let mut by_time_control: HashMap<String, Vec<&GameRecord>> = HashMap::new();
+    for game in games {
+        by_time_control.entry(game.time_control.clone()).or_default().push(game);
+    }
+
+    let mut snapshots = Vec::new();
+    let mut updated_checkpoints = HashMap::new();
+
+    for (time_control, tc_games) in by_time_control {
+        let checkpoint = checkpoints.get(&time_control).cloned().unwrap_or_default();
+        let mut ratings = checkpoint.ratings;
+        let mut last_month = checkpoint.last_month;
+
+        let mut months: Vec<&String> = tc_games
+            .iter()
+            .map(|g| &g.month)
+            .filter(|month| last_month.as_ref().map_or(true, |last| *month > last))
+            .collect();
+        months.sort();
+        months.dedup();
+
+        for month in months {
+            let period_games: Vec<GameRecord> = tc_games
+                .iter()
+                .filter(|g| &g.month == month)
+                .map(|&g| g.clone())
+                .collect();
+            let games_played = RatingPeriod::new(month.clone(), time_control.clone()).process(&period_games, &mut ratings);
+
+            snapshots.push(RatingSnapshot {
+                month: month.clone(),
+                time_control: time_control.clone(),
+                ratings: ratings.clone(),
+                games_played,
+            });
+
+            last_month = Some(month.clone());
+        }
+
+        updated_checkpoints.insert(time_control, TimeControlCheckpoint { last_month, ratings });
+    }
+
+    (snapshots, updated_checkpoints)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Glickman's published Glicko-2 worked example: a player rated 1500
+    /// (RD 200, volatility 0.06) plays three games in one rating period
+    /// against opponents of varying rating and RD, and the resulting
+    /// rating/RD/volatility are known to several decimal places. See
+    /// http://www.glicko.net/glicko/glicko2.pdf, "Example calculation."
+    #[test]
+    fn update_rating_matches_glickman_worked_example() {
+        let player = PlayerRating {
+            r: 1500.0,
+            rd: 200.0,
+            sigma: 0.06,
+        };
+
+        let opponent = |r: f64, rd: f64, score: f64| {
+            let (mu, phi) = PlayerRating { r, rd, sigma: 0.06 }.to_internal();
+            Opponent { mu, phi, score }
+        };
+
+        let opponents = [
+            opponent(1400.0, 30.0, 1.0),
+            opponent(1550.0, 100.0, 0.0),
+            opponent(1700.0, 300.0, 0.0),
+        ];
+
+        let updated = update_rating(player, &opponents);
+
+        assert!((updated.r - 1464.06).abs() < 0.01, "r = {}", updated.r);
+        assert!((updated.rd - 151.52).abs() < 0.01, "rd = {}", updated.rd);
+        assert!((updated.sigma - 0.05999).abs() < 1e-5, "sigma = {}", updated.sigma);
+    }
+
+    /// Same worked example, but pinning `solve_volatility` directly against
+    /// the paper's intermediate `v` and `delta` values rather than
+    /// recomputing them, so a regression in `update_rating`'s own
+    /// `v`/`delta` math can't mask a break in the solver itself.
+    #[test]
+    fn solve_volatility_matches_glickman_worked_example() {
+        let phi = 200.0 / SCALE;
+        let delta = -0.4834;
+        let v = 1.7785;
+        let sigma = 0.06;
+
+        let sigma_prime = solve_volatility(delta, phi, v, sigma);
+
+        assert!((sigma_prime - 0.05999).abs() < 1e-5, "sigma' = {}", sigma_prime);
+    }
+}
@@ -0,0 +1,228 @@
+//! Streaming ingestion of Parquet objects from S3.
+//!
+//! Rather than downloading a whole object to `/tmp` before parsing it, the
+//! reader here is backed directly by ranged `GetObject` calls: the footer
+//! and row-group metadata are fetched first (a few KB), then each row group
+//! is pulled with its own byte range and decoded as it arrives. This keeps
+//! disk usage at zero and memory usage bounded to whatever row groups are
+//! in flight, regardless of the object's total size.
+
+use aws_sdk_s3::Client;
+use bytes::Bytes;
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use log::{error, info};
+use parquet::errors::ParquetError;
+use parquet::file::reader::{ChunkReader, FileReader, Length, SerializedFileReader};
+use parquet::record::RowAccessor;
+use std::collections::HashMap;
+use std::io;
+use std::sync::{Arc, Mutex};
+use tokio::runtime::Handle;
+use tokio::sync::Semaphore;
+
+use crate::glicko2::GameRecord;
+use crate::Statistics;
+
+/// Tunables for the streaming ingestion path.
+#[derive(Debug, Clone, Copy)]
+pub struct IngestConfig {
+    /// How many row groups may be fetched and decoded concurrently, per
+    /// file, bounding memory usage independent of object size.
+    pub max_concurrent_row_groups: usize,
+}
+
+impl Default for IngestConfig {
+    fn default() -> Self {
+        IngestConfig {
+            max_concurrent_row_groups: 4,
+        }
+    }
+}
+
+/// A [`ChunkReader`] that services every read with a ranged S3 `GetObject`,
+/// so `SerializedFileReader` never needs the whole object in memory or on
+/// disk. `ChunkReader` is synchronous, so reads are bridged onto the
+/// current Tokio runtime with `block_in_place`.
+struct S3ChunkReader {
+    client: Client,
+    bucket: String,
+    key: String,
+    length: u64,
+    handle: Handle,
+}
+
+impl Length for S3ChunkReader {
+    fn len(&self) -> u64 {
+        self.length
+    }
+}
+
+impl ChunkReader for S3ChunkReader {
+    type T = io::Cursor<Bytes>;
+
+    fn get_read(&self, start: u64) -> parquet::errors::Result<Self::T> {
+        let remaining = self.length.saturating_sub(start);
+        Ok(io::Cursor::new(self.get_bytes(start, remaining as usize)?))
+    }
+
+    fn get_bytes(&self, start: u64, length: usize) -> parquet::errors::Result<Bytes> {
+        if length == 0 {
+            return Ok(Bytes::new());
+        }
+
+        let end = start + length as u64 - 1;
+        let range = format!("bytes={}-{}", start, end);
+        let client = self.client.clone();
+        let bucket = self.bucket.clone();
+        let key = self.key.clone();
+
+        tokio::task::block_in_place(|| {
+            self.handle.block_on(async move {
+                let resp = client
+                    .get_object()
+                    .bucket(bucket)
+                    .key(key)
+                    .range(range)
+                    .send()
+                    .await
+                    .map_err(|e| ParquetError::General(format!("S3 range GET failed: {e}")))?;
+
+                resp.body
+                    .collect()
+                    .await
+                    .map(|data| data.into_bytes())
+                    .map_err(|e| ParquetError::General(format!("failed to read S3 range response: {e}")))
+            })
+        })
+    }
+}
+
+/// Streams `key` from `bucket` row group by row group, decoding each one as
+/// its bytes arrive and folding the rows into `stats`/`games`. Up to
+/// `config.max_concurrent_row_groups` row groups are in flight at once.
+/// `multi_progress` is shared across all concurrently-running files so
+/// each one gets its own progress bar, tracking row groups completed.
+/// Every row group is attempted; if any of them fails, an error is returned
+/// once all have finished.
+pub async fn stream_and_process_parquet(
+    client: &Client,
+    bucket: &str,
+    key: &str,
+    stats: &Arc<Mutex<Statistics>>,
+    games: &Arc<Mutex<Vec<GameRecord>>>,
+    config: &IngestConfig,
+    multi_progress: &MultiProgress,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let head = client.head_object().bucket(bucket).key(key).send().await?;
+    let length = head.content_length().max(0) as u64;
+
+    info!("Streaming {} ({} bytes) by row group", key, length);
+
+    let reader = S3ChunkReader {
+        client: client.clone(),
+        bucket: bucket.to_string(),
+        key: key.to_string(),
+        length,
+        handle: Handle::current(),
+    };
+
+    // Only touches the footer, via a handful of ranged `get_bytes` calls.
+    let file_reader = Arc::new(SerializedFileReader::new(reader)?);
+    let metadata = file_reader.metadata();
+    let schema = metadata.file_metadata().schema_descr();
+
+    let mut column_map = HashMap::new();
+    for i in 0..schema.num_columns() {
+        column_map.insert(schema.column(i).name().to_string(), i);
+    }
+    for &col in &["player1", "player2", "outcome", "month", "time_control"] {
+        if !column_map.contains_key(col) {
+            return Err(format!("Missing required column: {}", col).into());
+        }
+    }
+
+    let semaphore = Arc::new(Semaphore::new(config.max_concurrent_row_groups.max(1)));
+    let num_row_groups = metadata.num_row_groups();
+
+    let pb = multi_progress.add(ProgressBar::new(num_row_groups as u64));
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template("{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {pos}/{len} row groups ({msg})")
+            .unwrap()
+            .progress_chars("#>-"),
+    );
+    pb.set_message(key.to_string());
+
+    let mut tasks = Vec::with_capacity(num_row_groups);
+
+    for row_group_idx in 0..num_row_groups {
+        let permit = semaphore.clone().acquire_owned().await?;
+        let file_reader = file_reader.clone();
+        let column_map = column_map.clone();
+        let stats = stats.clone();
+        let games = games.clone();
+        let key = key.to_string();
+
+        tasks.push(tokio::task::spawn_blocking(move || {
+            let _permit = permit;
+            process_row_group(&file_reader, row_group_idx, &column_map, &stats, &games)
+                .map_err(|e| format!("row group {} of {}: {}", row_group_idx, key, e))
+        }));
+    }
+
+    let mut failed_row_groups = 0;
+    for task in tasks {
+        if let Err(e) = task.await? {
+            error!("{}", e);
+            failed_row_groups += 1;
+        }
+        pb.inc(1);
+    }
+
+    pb.finish_and_clear();
+
+    if failed_row_groups > 0 {
+        return Err(format!("{} of {} row groups of {} failed", failed_row_groups, num_row_groups, key).into());
+    }
+
+    Ok(())
+}
+
+/// Decodes one row group and folds its rows into `stats`/`games`.
+fn process_row_group(
+    file_reader: &SerializedFileReader<S3ChunkReader>,
+    row_group_idx: usize,
+    column_map: &HashMap<String, usize>,
+    stats: &Arc<Mutex<Statistics>>,
+    games: &Arc<Mutex<Vec<GameRecord>>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let row_group_reader = file_reader.get_row_group(row_group_idx)?;
+    let mut iter = row_group_reader.get_row_iter(None)?;
+
+    while let Some(record) = iter.next() {
+        let player1 = record.get_string(column_map["player1"]).map_or("", |v| v.as_str()).to_string();
+        let player2 = record.get_string(column_map["player2"]).map_or("", |v| v.as_str()).to_string();
+        let outcome = record.get_float(column_map["outcome"]).map_or(0.0, |v| v);
+        let month = record.get_string(column_map["month"]).map_or("", |v| v.as_str()).to_string();
+        let time_control = record.get_string(column_map["time_control"]).map_or("", |v| v.as_str()).to_string();
+
+        let game = GameRecord {
+            player1,
+            player2,
+            outcome,
+            month,
+            time_control,
+        };
+
+        {
+            let mut stats = stats.lock().unwrap();
+            stats.total_rows += 1;
+            *stats.rows_per_month.entry(game.month.clone()).or_insert(0) += 1;
+            *stats.rows_per_time_control.entry(game.time_control.clone()).or_insert(0) += 1;
+        }
+
+        games.lock().unwrap().push(game);
+    }
+
+    Ok(())
+}
@@ -1,11 +1,12 @@
 use aws_config::meta::region::RegionProviderChain;
 use aws_sdk_s3::Client;
+use clap::Parser;
+use futures_util::stream::FuturesUnordered;
 use futures_util::StreamExt; // For chunk-by-chunk reading
-use indicatif::{ProgressBar, ProgressStyle};
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 use log::{error, info};
 use parquet::file::reader::{FileReader, SerializedFileReader};
 use parquet::record::RowAccessor;
-use serde::{Deserialize, Serialize};
 use simplelog::{ConfigBuilder, TermLogger, TerminalMode, ColorChoice, LevelFilter};
 use std::collections::HashMap;
 use std::env;
@@ -14,35 +15,72 @@ use std::io::Write; // Added Write trait
 use std::path::Path;
 use std::sync::{Arc, Mutex};
 use tokio::runtime::Runtime;
-
-/// Structure representing a game record.
-#[derive(Debug, Deserialize, Serialize)]
-struct GameRecord {
-    player1: String,
-    player2: String,
-    outcome: f32,
-    month: String,
-    time_control: String,
+use tokio::sync::Semaphore;
+
+mod decompress;
+mod glicko2;
+mod ingest;
+mod manifest;
+mod output;
+
+use glicko2::{GameRecord, PlayerRating};
+use ingest::IngestConfig;
+use manifest::Manifest;
+
+/// Command-line options for the ingestion pipeline.
+#[derive(Parser, Debug)]
+#[command(author, version, about)]
+struct Args {
+    /// Maximum number of Parquet row groups to fetch and decode
+    /// concurrently per file, bounding memory usage for large objects.
+    #[arg(long, default_value_t = 4)]
+    max_concurrent_row_groups: usize,
+
+    /// S3 prefix under which computed ratings are written, partitioned by
+    /// `time_control` and `month`.
+    #[arg(long, default_value = "ratings")]
+    output_prefix: String,
+
+    /// Maximum number of Parquet files to download/stream and process at
+    /// once.
+    #[arg(long, default_value_t = 4)]
+    concurrency: usize,
+
+    /// Reprocess every listed key, ignoring what the checkpoint manifest
+    /// says. By default, keys already recorded in the manifest with a
+    /// matching ETag and size are skipped.
+    #[arg(long)]
+    force: bool,
+
+    /// Path to the local checkpoint/resume manifest.
+    #[arg(long, default_value = "manifest.json")]
+    manifest_path: String,
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Initialize logger
     init_logging();
 
+    let args = Args::parse();
+
     // Create a Tokio runtime
     let rt = Runtime::new()?;
-    rt.block_on(async_main())?;
+    rt.block_on(async_main(args))?;
 
     Ok(())
 }
 
 /// Main async workflow
-async fn async_main() -> Result<(), Box<dyn std::error::Error>> {
+async fn async_main(args: Args) -> Result<(), Box<dyn std::error::Error>> {
     // Configuration
     let bucket = "sqlite-chess-data";      // S3 bucket name
     let prefix = "game-data/";             // S3 prefix for game data
     let aws_region = "us-east-2";          // AWS region
 
+    let ingest_config = IngestConfig {
+        max_concurrent_row_groups: args.max_concurrent_row_groups,
+    };
+
     // Initialize AWS S3 client
     let region_provider = RegionProviderChain::default_provider().or_else(aws_region);
     let shared_config = aws_config::from_env().region(region_provider).load().await;
@@ -57,23 +95,100 @@ async fn async_main() -> Result<(), Box<dyn std::error::Error>> {
         return Ok(());
     }
 
-    info!("Found {} Parquet files to process.", parquet_keys.len());
+    info!("Found {} Parquet files under the prefix.", parquet_keys.len());
+
+    // Load the checkpoint manifest and diff the listing against it so a
+    // normal run only (re)processes new or changed keys by default; pass
+    // `--force` to ignore the manifest and reprocess everything.
+    let manifest_path = Path::new(&args.manifest_path);
+    let manifest = Manifest::load(manifest_path)?;
+    let to_process = manifest::filter_unprocessed(&client, bucket, parquet_keys, &manifest, args.force).await?;
+
+    if to_process.is_empty() {
+        info!("Every listed key is already up to date in the manifest; nothing to do.");
+        return Ok(());
+    }
+
+    info!("Processing {} Parquet file(s).", to_process.len());
+    let manifest = Arc::new(Mutex::new(manifest));
 
-    // Initialize statistics
+    // Statistics only reflect rows seen this run.
     let stats = Arc::new(Mutex::new(Statistics::new()));
 
-    // Process each Parquet file
-    for key in parquet_keys {
-        info!("Processing file: {}", key);
-        match download_and_process_parquet(&client, bucket, &key, &stats).await {
-            Ok(_) => info!("Successfully processed: {}", key),
-            Err(e) => error!("Failed to process {}: {}", key, e),
+    // Process Parquet files concurrently, bounded by `args.concurrency`.
+    // Row-group byte ranges come from `RowGroupMetadata`, which is computed
+    // against the *decompressed* layout of the file, so a `.zst`/`.gz`
+    // object can't be served by ranged reads the way `stream_and_process_parquet`
+    // does — those keys (and any that already exist on the local
+    // filesystem, e.g. during local testing without S3) go through the
+    // whole-file temp-download path, which decompresses before parsing.
+    // Every other key streams row group by row group straight from S3.
+    // Every in-flight file renders its own bar on the shared
+    // `MultiProgress`, and a failure on one key is logged without aborting
+    // the rest of the batch. Each key's decoded games are collected into
+    // their own buffer, then folded into `new_games` — the games ingested
+    // this run — on success.
+    let multi_progress = Arc::new(MultiProgress::new());
+    let semaphore = Arc::new(Semaphore::new(args.concurrency.max(1)));
+    let mut tasks = FuturesUnordered::new();
+    let new_games = Arc::new(Mutex::new(Vec::<GameRecord>::new()));
+
+    for (key, e_tag, size) in to_process {
+        let client = client.clone();
+        let bucket = bucket.to_string();
+        let stats = stats.clone();
+        let key_games = Arc::new(Mutex::new(Vec::<GameRecord>::new()));
+        let multi_progress = multi_progress.clone();
+        let ingest_config = ingest_config;
+        let semaphore = semaphore.clone();
+        let manifest = manifest.clone();
+        let new_games = new_games.clone();
+
+        tasks.push(tokio::spawn(async move {
+            let _permit = semaphore.acquire_owned().await.expect("semaphore closed");
+
+            info!("Processing file: {}", key);
+            let needs_whole_file = Path::new(&key).exists() || decompress::Compression::from_key(&key) != decompress::Compression::None;
+            let result = if needs_whole_file {
+                download_and_process_parquet(&client, &bucket, &key, &stats, &key_games, &multi_progress).await
+            } else {
+                ingest::stream_and_process_parquet(&client, &bucket, &key, &stats, &key_games, &ingest_config, &multi_progress).await
+            };
+
+            match result {
+                Ok(_) => {
+                    info!("Successfully processed: {}", key);
+                    new_games.lock().unwrap().extend(key_games.lock().unwrap().drain(..));
+                    manifest.lock().unwrap().record(&key, e_tag, size);
+                }
+                Err(e) => error!("Failed to process {}: {}", key, e),
+            }
+        }));
+    }
+
+    while let Some(join_result) = tasks.next().await {
+        if let Err(e) = join_result {
+            error!("Parquet processing task panicked: {}", e);
         }
     }
 
     // Print out the aggregated statistics
     print_statistics(&stats.lock().unwrap());
 
+    // Run the Glicko-2 rating engine over just the games ingested this
+    // run, resuming each time control from its checkpoint in the manifest
+    // rather than replaying every game ever seen. Persist the updated
+    // checkpoints, print the resulting ratings, and publish this run's new
+    // snapshots to S3 as partitioned Parquet output.
+    let mut manifest = manifest.lock().unwrap();
+    let new_games = new_games.lock().unwrap();
+    let (snapshots, updated_checkpoints) = glicko2::process_all_periods(&new_games, manifest.checkpoints());
+    manifest.update_checkpoints(updated_checkpoints);
+    manifest.save(manifest_path)?;
+
+    print_ratings(&snapshots);
+    output::write_ratings(&client, bucket, &args.output_prefix, &snapshots).await?;
+
     Ok(())
 }
 
@@ -111,8 +226,11 @@ async fn list_parquet_files(client: &Client, bucket: &str, prefix: &str) -> Resu
     Ok(keys)
 }
 
-/// Downloads a Parquet file from S3 and processes it to update statistics.
-async fn download_and_process_parquet(client: &Client, bucket: &str, key: &str, stats: &Arc<Mutex<Statistics>>) -> Result<(), Box<dyn std::error::Error>> {
+/// Downloads a Parquet file from S3 and processes it to update statistics
+/// and the accumulated game log. `multi_progress` is shared across all
+/// concurrently-running downloads so each one gets its own progress bar
+/// rendered in the same terminal region.
+async fn download_and_process_parquet(client: &Client, bucket: &str, key: &str, stats: &Arc<Mutex<Statistics>>, games: &Arc<Mutex<Vec<GameRecord>>>, multi_progress: &MultiProgress) -> Result<(), Box<dyn std::error::Error>> {
     // Download the file to a temporary location
     let temp_dir = env::temp_dir();
     let file_name = Path::new(key).file_name().unwrap().to_str().unwrap();
@@ -134,12 +252,13 @@ async fn download_and_process_parquet(client: &Client, bucket: &str, key: &str,
     let content_length = resp.content_length() as u64; // Ensure u64 type
     info!("Downloading {} ({} bytes)", key, content_length);
 
-    // Set up a progress bar
-    let pb = if content_length > 0 {
+    // Set up a progress bar, registered with the shared MultiProgress so it
+    // renders alongside every other in-flight download.
+    let pb = multi_progress.add(if content_length > 0 {
         ProgressBar::new(content_length)
     } else {
         ProgressBar::new_spinner()
-    };
+    });
     let style = if content_length > 0 {
         ProgressStyle::default_bar()
             .template("{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({eta})")
@@ -174,7 +293,7 @@ async fn download_and_process_parquet(client: &Client, bucket: &str, key: &str,
     info!("Download complete for {}", key);
 
     // Process the Parquet file
-    match process_parquet_file(&local_path, stats) {
+    match process_parquet_file(&local_path, key, stats, games) {
         Ok(_) => info!("Processed Parquet file: {}", key),
         Err(e) => error!("Error processing Parquet file {}: {}", key, e),
     }
@@ -185,11 +304,19 @@ async fn download_and_process_parquet(client: &Client, bucket: &str, key: &str,
     Ok(())
 }
 
-/// Processes a Parquet file and updates the statistics.
-fn process_parquet_file(file_path: &Path, stats: &Arc<Mutex<Statistics>>) -> Result<(), Box<dyn std::error::Error>> {
-    // Open the Parquet file
-    let file = File::open(file_path)?;
-    let reader = SerializedFileReader::new(file)?;
+/// Processes a Parquet file, updating the statistics and appending every
+/// row to the accumulated game log for later rating calculation. `key` is
+/// used only to detect a `.zst`/`.gz` suffix; when present, the file's
+/// bytes are transparently decompressed into memory before parsing rather
+/// than being decompressed to a second file on disk.
+fn process_parquet_file(file_path: &Path, key: &str, stats: &Arc<Mutex<Statistics>>, games: &Arc<Mutex<Vec<GameRecord>>>) -> Result<(), Box<dyn std::error::Error>> {
+    // Open the (possibly compressed) Parquet file and decompress it into
+    // memory if needed.
+    let raw = std::fs::read(file_path)?;
+    let compression = decompress::Compression::from_key(key);
+    let bytes = decompress::decompress(raw, compression)?;
+
+    let reader = SerializedFileReader::new(bytes)?;
     let metadata = reader.metadata();
     let schema = metadata.file_metadata().schema_descr();
 
@@ -226,20 +353,24 @@ fn process_parquet_file(file_path: &Path, stats: &Arc<Mutex<Statistics>>) -> Res
             time_control,
         };
 
-        let mut stats = stats.lock().unwrap();
-        stats.total_rows += 1;
-        *stats.rows_per_month.entry(game.month.clone()).or_insert(0) += 1;
-        *stats.rows_per_time_control.entry(game.time_control.clone()).or_insert(0) += 1;
+        {
+            let mut stats = stats.lock().unwrap();
+            stats.total_rows += 1;
+            *stats.rows_per_month.entry(game.month.clone()).or_insert(0) += 1;
+            *stats.rows_per_time_control.entry(game.time_control.clone()).or_insert(0) += 1;
+        }
+
+        games.lock().unwrap().push(game);
     }
 
     Ok(())
 }
 
 /// Structure to hold aggregated statistics.
-struct Statistics {
-    total_rows: usize,
-    rows_per_month: HashMap<String, usize>,
-    rows_per_time_control: HashMap<String, usize>,
+pub(crate) struct Statistics {
+    pub(crate) total_rows: usize,
+    pub(crate) rows_per_month: HashMap<String, usize>,
+    pub(crate) rows_per_time_control: HashMap<String, usize>,
 }
 
 impl Statistics {
@@ -269,6 +400,28 @@ fn print_statistics(stats: &Statistics) {
     println!("==================================");
 }
 
+/// Prints the final Glicko-2 rating for every player, grouped by time
+/// control. The final state for a time control is whatever its last
+/// processed snapshot holds.
+fn print_ratings(snapshots: &[glicko2::RatingSnapshot]) {
+    let mut final_ratings: HashMap<&str, &HashMap<String, PlayerRating>> = HashMap::new();
+    for snapshot in snapshots {
+        final_ratings.insert(&snapshot.time_control, &snapshot.ratings);
+    }
+
+    println!("\n===== Final Ratings =====");
+    for (time_control, ratings) in final_ratings {
+        println!("\nTime control: {}", time_control);
+        for (player, rating) in ratings {
+            println!(
+                "  {}: r={:.1} rd={:.1} sigma={:.4}",
+                player, rating.r, rating.rd, rating.sigma
+            );
+        }
+    }
+    println!("==========================");
+}
+
 /// Initializes a logger that prints timestamps and log levels to the terminal.
 fn init_logging() {
     let mut config_builder = ConfigBuilder::new();
@@ -0,0 +1,118 @@
+//! Checkpoint/resume manifest for incremental ingestion.
+//!
+//! Every successfully processed S3 key is recorded here along with its
+//! ETag and size. On startup, the current object listing is diffed against
+//! this ledger so a crash mid-run — or a daily incremental ingest of newly
+//! uploaded game dumps — only reprocesses new or changed keys.
+//!
+//! The rating engine needs to keep seeing returning players' history
+//! across runs too, but the ledger does *not* do that by keeping every
+//! game ever ingested around: for large buckets that would make the
+//! manifest itself grow unboundedly with total history, fully
+//! loaded/re-serialized as one JSON blob on every run regardless of how
+//! little changed. Instead, the ledger stores each time control's
+//! [`TimeControlCheckpoint`](crate::glicko2::TimeControlCheckpoint) — the
+//! last month processed and every player's rating as of the end of it —
+//! so `process_all_periods` can resume forward from one stored rating per
+//! player instead of replaying every game ever seen. The ledger itself is
+//! written write-temp-then-rename so a crash mid-write can never leave a
+//! corrupt manifest behind.
+
+use aws_sdk_s3::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::glicko2::TimeControlCheckpoint;
+
+/// What was recorded about a successfully processed key.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ManifestEntry {
+    pub e_tag: String,
+    pub size: i64,
+}
+
+/// The ledger of successfully processed keys, keyed by S3 object key, plus
+/// every time control's rating-engine checkpoint.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    entries: HashMap<String, ManifestEntry>,
+    checkpoints: HashMap<String, TimeControlCheckpoint>,
+}
+
+impl Manifest {
+    /// Loads the manifest from `path`, or starts empty if it doesn't exist
+    /// yet (e.g. the very first run).
+    pub fn load(path: &Path) -> Result<Manifest, Box<dyn std::error::Error>> {
+        if !path.exists() {
+            return Ok(Manifest::default());
+        }
+        let data = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&data)?)
+    }
+
+    /// Atomically writes the manifest to `path`: the new contents are
+    /// written to a sibling temp file first, then renamed into place, so a
+    /// crash mid-write never corrupts the existing ledger.
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let tmp_path: PathBuf = path.with_extension("json.tmp");
+        fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        fs::rename(&tmp_path, path)?;
+        Ok(())
+    }
+
+    /// Records that `key` was processed successfully with the given ETag
+    /// and size. Replaces whatever was previously recorded for `key`, so
+    /// reprocessing a changed (or `--force`d) key can't leave a stale entry
+    /// from an earlier version of the file behind.
+    pub fn record(&mut self, key: &str, e_tag: String, size: i64) {
+        self.entries.insert(key.to_string(), ManifestEntry { e_tag, size });
+    }
+
+    /// True if `key` is already recorded with a matching ETag and size,
+    /// i.e. it can be safely skipped.
+    pub fn is_up_to_date(&self, key: &str, e_tag: &str, size: i64) -> bool {
+        matches!(self.entries.get(key), Some(entry) if entry.e_tag == e_tag && entry.size == size)
+    }
+
+    /// Every time control's checkpoint as of the end of the last run, for
+    /// the rating engine to resume forward from.
+    pub fn checkpoints(&self) -> &HashMap<String, TimeControlCheckpoint> {
+        &self.checkpoints
+    }
+
+    /// Merges freshly-computed checkpoints in after a run, overwriting only
+    /// the time controls actually touched this run and leaving every other
+    /// time control's checkpoint untouched.
+    pub fn update_checkpoints(&mut self, updated: HashMap<String, TimeControlCheckpoint>) {
+        self.checkpoints.extend(updated);
+    }
+}
+
+/// Looks up the current ETag/size of every key in `keys` and returns only
+/// those the manifest doesn't already have recorded with a matching
+/// ETag/size, alongside the looked-up ETag/size so the caller can record
+/// them again once processing succeeds. When `force` is set every key is
+/// returned regardless of what the manifest says.
+pub async fn filter_unprocessed(
+    client: &Client,
+    bucket: &str,
+    keys: Vec<String>,
+    manifest: &Manifest,
+    force: bool,
+) -> Result<Vec<(String, String, i64)>, Box<dyn std::error::Error>> {
+    let mut to_process = Vec::new();
+
+    for key in keys {
+        let head = client.head_object().bucket(bucket).key(&key).send().await?;
+        let e_tag = head.e_tag().unwrap_or_default().to_string();
+        let size = head.content_length();
+
+        if force || !manifest.is_up_to_date(&key, &e_tag, size) {
+            to_process.push((key, e_tag, size));
+        }
+    }
+
+    Ok(to_process)
+}
@@ -0,0 +1,311 @@
+//! Writes computed ratings back to S3 as partitioned Parquet output.
+//!
+//! Each [`glicko2::RatingSnapshot`](crate::glicko2::RatingSnapshot) is
+//! serialized to a `(player, r, rd, sigma, games_played)` Parquet file and
+//! uploaded under `{prefix}/time_control={tc}/month={month}/part.parquet`.
+//! Rows are split across multiple row groups of bounded size, and the
+//! `SerializedFileWriter` writes directly into a multipart-upload-backed
+//! sink that ships each completed part to S3 as soon as it's full — so a
+//! large partition is never held in memory all at once, the way a
+//! buffer-then-chunk approach would. A `_SUCCESS` marker object is written
+//! per partition once the upload completes, so downstream jobs can detect
+//! finished partitions without reading the data itself.
+
+use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::types::{CompletedMultipartUpload, CompletedPart};
+use aws_sdk_s3::Client;
+use log::info;
+use parquet::basic::Repetition;
+use parquet::column::writer::ColumnWriter;
+use parquet::data_type::ByteArray;
+use parquet::errors::ParquetError;
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::SerializedFileWriter;
+use parquet::schema::types::Type;
+use std::io;
+use std::sync::{Arc, Mutex};
+use tokio::runtime::Handle;
+
+use crate::glicko2::RatingSnapshot;
+
+/// Size at which a multipart upload part is shipped (8 MiB, above S3's 5 MiB
+/// minimum for every part but the last).
+const PART_SIZE: usize = 8 * 1024 * 1024;
+/// Rows per Parquet row group. `SerializedFileWriter` only flushes a row
+/// group's encoded bytes to the underlying `Write` once it's closed, so
+/// this is what bounds how much of the snapshot is held in memory at once.
+const ROWS_PER_ROW_GROUP: usize = 50_000;
+
+/// Writes every snapshot to its `time_control=.../month=.../part.parquet`
+/// partition under `prefix`, followed by a `_SUCCESS` marker.
+pub async fn write_ratings(
+    client: &Client,
+    bucket: &str,
+    prefix: &str,
+    snapshots: &[RatingSnapshot],
+) -> Result<(), Box<dyn std::error::Error>> {
+    for snapshot in snapshots {
+        let partition = format!(
+            "{}/time_control={}/month={}",
+            prefix, snapshot.time_control, snapshot.month
+        );
+        let data_key = format!("{}/part.parquet", partition);
+        let success_key = format!("{}/_SUCCESS", partition);
+
+        stream_snapshot(client, bucket, &data_key, snapshot).await?;
+        client
+            .put_object()
+            .bucket(bucket)
+            .key(&success_key)
+            .body(ByteStream::from(Vec::new()))
+            .send()
+            .await?;
+
+        info!("Wrote ratings partition s3://{}/{}", bucket, partition);
+    }
+
+    Ok(())
+}
+
+/// A multipart upload that implements [`io::Write`] so a `SerializedFileWriter`
+/// can write directly into it: bytes are buffered only until a part's worth
+/// has accumulated, at which point that part is shipped to S3 and the
+/// buffer is freed, rather than the whole file being built up first. Writes
+/// are synchronous, so the upload calls are bridged onto the current Tokio
+/// runtime the same way `S3ChunkReader` bridges its ranged reads in
+/// `ingest.rs`.
+struct MultipartWriter {
+    client: Client,
+    bucket: String,
+    key: String,
+    upload_id: String,
+    part_number: i32,
+    buffer: Vec<u8>,
+    completed_parts: Vec<CompletedPart>,
+    handle: Handle,
+}
+
+impl MultipartWriter {
+    async fn create(client: &Client, bucket: &str, key: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let create = client.create_multipart_upload().bucket(bucket).key(key).send().await?;
+        let upload_id = create.upload_id().ok_or("S3 did not return an upload id")?.to_string();
+
+        Ok(MultipartWriter {
+            client: client.clone(),
+            bucket: bucket.to_string(),
+            key: key.to_string(),
+            upload_id,
+            part_number: 1,
+            buffer: Vec::with_capacity(PART_SIZE),
+            completed_parts: Vec::new(),
+            handle: Handle::current(),
+        })
+    }
+
+    /// Ships the current buffer as a part if it's reached `PART_SIZE`, or
+    /// unconditionally when `force` is set (used once, for the final
+    /// trailing part on close).
+    fn flush_part(&mut self, force: bool) -> io::Result<()> {
+        if self.buffer.is_empty() || (!force && self.buffer.len() < PART_SIZE) {
+            return Ok(());
+        }
+
+        let part_number = self.part_number;
+        let body = ByteStream::from(std::mem::take(&mut self.buffer));
+        let client = self.client.clone();
+        let bucket = self.bucket.clone();
+        let key = self.key.clone();
+        let upload_id = self.upload_id.clone();
+
+        let completed = tokio::task::block_in_place(|| {
+            self.handle.block_on(async move {
+                let resp = client
+                    .upload_part()
+                    .bucket(bucket)
+                    .key(key)
+                    .upload_id(upload_id)
+                    .part_number(part_number)
+                    .body(body)
+                    .send()
+                    .await
+                    .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("S3 upload_part failed: {e}")))?;
+
+                Ok::<_, io::Error>(
+                    CompletedPart::builder()
+                        .set_e_tag(resp.e_tag().map(str::to_string))
+                        .part_number(part_number)
+                        .build(),
+                )
+            })
+        })?;
+
+        self.completed_parts.push(completed);
+        self.part_number += 1;
+        Ok(())
+    }
+
+    /// Flushes any trailing bytes as the last part and completes the
+    /// upload. An upload to which no bytes were ever written has no parts
+    /// and is aborted instead, since S3 rejects a multipart completion with
+    /// zero parts.
+    async fn finish(mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.flush_part(true)?;
+
+        if self.completed_parts.is_empty() {
+            self.client
+                .abort_multipart_upload()
+                .bucket(&self.bucket)
+                .key(&self.key)
+                .upload_id(&self.upload_id)
+                .send()
+                .await?;
+            return Ok(());
+        }
+
+        self.client
+            .complete_multipart_upload()
+            .bucket(&self.bucket)
+            .key(&self.key)
+            .upload_id(&self.upload_id)
+            .multipart_upload(
+                CompletedMultipartUpload::builder()
+                    .set_parts(Some(self.completed_parts.clone()))
+                    .build(),
+            )
+            .send()
+            .await?;
+
+        Ok(())
+    }
+}
+
+impl io::Write for MultipartWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        self.flush_part(false)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A cloneable handle to a [`MultipartWriter`] so it can be handed to
+/// `SerializedFileWriter` (which takes its `Write` sink by value) while a
+/// second handle is kept around to call `finish()` on once the writer is
+/// done with it.
+#[derive(Clone)]
+struct SharedMultipartWriter(Arc<Mutex<MultipartWriter>>);
+
+impl io::Write for SharedMultipartWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.lock().unwrap().flush()
+    }
+}
+
+/// Serializes `snapshot` to Parquet in row-group-sized batches, writing
+/// each row group's encoded bytes directly into a multipart upload as soon
+/// as it closes, rather than buffering the whole encoded file first.
+async fn stream_snapshot(
+    client: &Client,
+    bucket: &str,
+    key: &str,
+    snapshot: &RatingSnapshot,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let schema = Arc::new(
+        Type::group_type_builder("rating")
+            .with_fields(vec![
+                Arc::new(
+                    Type::primitive_type_builder("player", parquet::basic::Type::BYTE_ARRAY)
+                        .with_repetition(Repetition::REQUIRED)
+                        .with_converted_type(parquet::basic::ConvertedType::UTF8)
+                        .build()?,
+                ),
+                Arc::new(
+                    Type::primitive_type_builder("r", parquet::basic::Type::DOUBLE)
+                        .with_repetition(Repetition::REQUIRED)
+                        .build()?,
+                ),
+                Arc::new(
+                    Type::primitive_type_builder("rd", parquet::basic::Type::DOUBLE)
+                        .with_repetition(Repetition::REQUIRED)
+                        .build()?,
+                ),
+                Arc::new(
+                    Type::primitive_type_builder("sigma", parquet::basic::Type::DOUBLE)
+                        .with_repetition(Repetition::REQUIRED)
+                        .build()?,
+                ),
+                Arc::new(
+                    Type::primitive_type_builder("games_played", parquet::basic::Type::INT64)
+                        .with_repetition(Repetition::REQUIRED)
+                        .build()?,
+                ),
+            ])
+            .build()?,
+    );
+
+    let mut players: Vec<&String> = snapshot.ratings.keys().collect();
+    players.sort();
+
+    let writer = MultipartWriter::create(client, bucket, key).await?;
+    let shared = SharedMultipartWriter(Arc::new(Mutex::new(writer)));
+
+    {
+        let props = Arc::new(WriterProperties::builder().build());
+        let mut file_writer = SerializedFileWriter::new(shared.clone(), schema, props)?;
+
+        for batch in players.chunks(ROWS_PER_ROW_GROUP) {
+            let player_values: Vec<ByteArray> = batch.iter().map(|p| ByteArray::from(p.as_str())).collect();
+            let r_values: Vec<f64> = batch.iter().map(|p| snapshot.ratings[*p].r).collect();
+            let rd_values: Vec<f64> = batch.iter().map(|p| snapshot.ratings[*p].rd).collect();
+            let sigma_values: Vec<f64> = batch.iter().map(|p| snapshot.ratings[*p].sigma).collect();
+            let games_played_values: Vec<i64> = batch
+                .iter()
+                .map(|p| *snapshot.games_played.get(*p).unwrap_or(&0) as i64)
+                .collect();
+
+            // Columns are visited in schema declaration order: player, r,
+            // rd, sigma, games_played.
+            let double_columns = [&r_values, &rd_values, &sigma_values];
+            let mut double_idx = 0;
+
+            let mut row_group_writer = file_writer.next_row_group()?;
+            while let Some(mut column_writer) = row_group_writer.next_column()? {
+                match column_writer.untyped() {
+                    ColumnWriter::ByteArrayColumnWriter(w) => {
+                        w.write_batch(&player_values, None, None)?;
+                    }
+                    ColumnWriter::DoubleColumnWriter(w) => {
+                        w.write_batch(double_columns[double_idx], None, None)?;
+                        double_idx += 1;
+                    }
+                    ColumnWriter::Int64ColumnWriter(w) => {
+                        w.write_batch(&games_played_values, None, None)?;
+                    }
+                    _ => unreachable!("unexpected column type in ratings schema"),
+                }
+                column_writer.close()?;
+            }
+            // Flushes this row group's encoded bytes into the
+            // `MultipartWriter`, which ships them off as a part once
+            // they've accumulated to `PART_SIZE`.
+            row_group_writer.close()?;
+        }
+
+        file_writer.close()?;
+    }
+
+    let writer = Arc::try_unwrap(shared.0)
+        .map_err(|_| ParquetError::General("multipart writer outlived the file writer".to_string()))?
+        .into_inner()
+        .unwrap();
+    writer.finish().await?;
+
+    Ok(())
+}
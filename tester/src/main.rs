@@ -1,7 +1,12 @@
+use clap::Parser;
 use std::fs::File;
 use std::io;
 use std::path::Path;
 
+mod report;
+
+use report::build_report;
+
 fn g(rd: f64) -> f64 {
     1.0 / (1.0 + 3.0 * rd.powi(2) / std::f64::consts::PI.powi(2)).sqrt()
 }
@@ -10,63 +15,88 @@ fn expected_score(r_i: f64, rd_i: f64, r_j: f64, rd_j: f64) -> f64 {
     let combined_rd = (rd_i.powi(2) + rd_j.powi(2)).sqrt();
     let g_combined = g(combined_rd);
     let delta_r = (r_i - r_j) / 400.0;
-    
+
     1.0 / (1.0 + 10f64.powf(-g_combined * delta_r))
 }
 
-fn binary_cross_entropy_loss(r_i: f64, rd_i: f64, r_j: f64, rd_j: f64, outcome: f64) -> f64 {
-    // Ensure the outcome is either 0.0 (loss), 1.0 (win), or 0.5 (draw)
-    assert!(outcome == 0.0 || outcome == 1.0 || outcome == 0.5, "Outcome must be 0.0, 1.0, or 0.5");
+/// Command-line options for the calibration-metrics report.
+#[derive(Parser, Debug)]
+#[command(author, version, about)]
+struct Args {
+    /// Path to the games CSV, with one `r_i,rd_i,r_j,rd_j,outcome` row per
+    /// game.
+    #[arg(default_value = "games.csv")]
+    input: String,
 
-    let p_a = expected_score(r_i, rd_i, r_j, rd_j);
+    /// Number of equal-width bins to bucket predictions into for the
+    /// calibration table (the default of 10 gives deciles).
+    #[arg(long, default_value_t = 10)]
+    bins: usize,
 
-    // Binary cross-entropy loss
-    -outcome * p_a.ln() - (1.0 - outcome) * (1.0 - p_a).ln()
+    /// Emit the report as JSON instead of human-readable text.
+    #[arg(long)]
+    json: bool,
 }
 
-fn calculate_mean_binary_cross_entropy(file_path: &str) -> io::Result<f64> {
-    // Open the file
-    let path = Path::new(file_path);
-    let file = File::open(&path)?;
+/// Reads `path` and computes the predicted `expected_score` against the
+/// recorded outcome for every game.
+fn load_games(path: &str) -> io::Result<Vec<(f64, f64)>> {
+    let file = File::open(Path::new(path))?;
     let mut rdr = csv::ReaderBuilder::new().has_headers(false).from_reader(file);
 
-    let mut total_loss = 0.0;
-    let mut count = 0;
+    let mut games = Vec::new();
 
-    // Process each record in the CSV file
     for result in rdr.records() {
-        let record = result?; // Unwrap the record or return the error
+        let record = result?;
 
         if record.len() != 5 {
             eprintln!("Invalid line format: {:?}", record);
             continue;
         }
 
-        // Parse ratings and outcome
         let r_i: f64 = record[0].parse().expect("Invalid rating for player 1");
         let rd_i: f64 = record[1].parse().expect("Invalid deviation for player 1");
         let r_j: f64 = record[2].parse().expect("Invalid rating for player 2");
         let rd_j: f64 = record[3].parse().expect("Invalid deviation for player 2");
         let outcome: f64 = record[4].parse().expect("Invalid outcome");
 
-        // Compute binary cross-entropy loss
-        let loss = binary_cross_entropy_loss(r_i, rd_i, r_j, rd_j, outcome);
-        total_loss += loss;
-        count += 1;
+        // Outcome must be a loss, draw, or win (0.0, 0.5, or 1.0) — anything
+        // else is a corrupt row and would silently skew log_loss/brier_score
+        // (log_loss can even go negative) rather than failing loudly.
+        if outcome != 0.0 && outcome != 0.5 && outcome != 1.0 {
+            eprintln!("Invalid outcome {} in line: {:?}", outcome, record);
+            continue;
+        }
+
+        games.push((expected_score(r_i, rd_i, r_j, rd_j), outcome));
     }
 
-    if count == 0 {
+    if games.is_empty() {
         return Err(io::Error::new(io::ErrorKind::InvalidInput, "No valid games found"));
     }
 
-    Ok(total_loss / count as f64)
+    Ok(games)
 }
 
 fn main() {
-    let file_path = "games.csv"; // Replace with your file path
+    let args = Args::parse();
+
+    let games = match load_games(&args.input) {
+        Ok(games) => games,
+        Err(e) => {
+            eprintln!("Error loading games from {}: {}", args.input, e);
+            std::process::exit(1);
+        }
+    };
 
-    match calculate_mean_binary_cross_entropy(file_path) {
-        Ok(mean_loss) => println!("Mean Binary Cross-Entropy Loss: {:.6}", mean_loss),
-        Err(e) => eprintln!("Error calculating mean loss: {}", e),
+    let report = build_report(&games, args.bins);
+
+    if args.json {
+        match serde_json::to_string_pretty(&report) {
+            Ok(json) => println!("{}", json),
+            Err(e) => eprintln!("Error serializing report to JSON: {}", e),
+        }
+    } else {
+        print!("{}", report.to_text());
     }
 }
@@ -0,0 +1,186 @@
+//! Calibration-metrics report for the rating model's win-probability
+//! predictions.
+//!
+//! Grown from a single mean binary-cross-entropy number into a full
+//! report: log-loss, Brier score, classification accuracy (draws scored as
+//! half credit), and a reliability table that buckets predictions into
+//! equal-width bins of `expected_score` (deciles by default) and compares
+//! predicted vs. actual win rate per bucket.
+
+use serde::Serialize;
+
+/// One row of the calibration/reliability table.
+#[derive(Debug, Serialize)]
+pub struct CalibrationBin {
+    pub bin_start: f64,
+    pub bin_end: f64,
+    pub count: usize,
+    pub mean_predicted: f64,
+    pub actual_win_rate: f64,
+}
+
+/// The full evaluation report over one pass of games.
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub games: usize,
+    pub log_loss: f64,
+    pub brier_score: f64,
+    pub accuracy: f64,
+    pub calibration: Vec<CalibrationBin>,
+}
+
+/// Per-bin running totals while scanning games, so the whole report is
+/// built in a single pass.
+struct BinAccumulator {
+    count: usize,
+    predicted_sum: f64,
+    actual_sum: f64,
+}
+
+impl BinAccumulator {
+    fn new() -> Self {
+        BinAccumulator {
+            count: 0,
+            predicted_sum: 0.0,
+            actual_sum: 0.0,
+        }
+    }
+}
+
+/// Builds a [`Report`] from one pass over `(expected_score, outcome)`
+/// pairs, bucketing predictions into `bins` equal-width bins.
+pub fn build_report(games: &[(f64, f64)], bins: usize) -> Report {
+    let bins = bins.max(1);
+    let mut bin_accumulators: Vec<BinAccumulator> = (0..bins).map(|_| BinAccumulator::new()).collect();
+
+    let mut log_loss_sum = 0.0;
+    let mut brier_sum = 0.0;
+    let mut accuracy_sum = 0.0;
+
+    for &(p, outcome) in games {
+        log_loss_sum += -outcome * p.ln() - (1.0 - outcome) * (1.0 - p).ln();
+        brier_sum += (p - outcome).powi(2);
+
+        // Draws (outcome == 0.5) are neither a correct nor incorrect win/loss
+        // call, so they're scored as half credit regardless of the
+        // prediction.
+        accuracy_sum += if outcome == 0.5 {
+            0.5
+        } else {
+            let predicted_class = if p >= 0.5 { 1.0 } else { 0.0 };
+            if predicted_class == outcome {
+                1.0
+            } else {
+                0.0
+            }
+        };
+
+        let bin_idx = ((p * bins as f64) as usize).min(bins - 1);
+        let acc = &mut bin_accumulators[bin_idx];
+        acc.count += 1;
+        acc.predicted_sum += p;
+        acc.actual_sum += outcome;
+    }
+
+    let n = (games.len().max(1)) as f64;
+
+    let calibration = bin_accumulators
+        .into_iter()
+        .enumerate()
+        .map(|(i, acc)| CalibrationBin {
+            bin_start: i as f64 / bins as f64,
+            bin_end: (i + 1) as f64 / bins as f64,
+            count: acc.count,
+            mean_predicted: if acc.count > 0 { acc.predicted_sum / acc.count as f64 } else { 0.0 },
+            actual_win_rate: if acc.count > 0 { acc.actual_sum / acc.count as f64 } else { 0.0 },
+        })
+        .collect();
+
+    Report {
+        games: games.len(),
+        log_loss: log_loss_sum / n,
+        brier_score: brier_sum / n,
+        accuracy: accuracy_sum / n,
+        calibration,
+    }
+}
+
+impl Report {
+    /// Renders the report as human-readable text.
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        out.push_str(&format!("Games evaluated: {}\n", self.games));
+        out.push_str(&format!("Log loss:        {:.6}\n", self.log_loss));
+        out.push_str(&format!("Brier score:     {:.6}\n", self.brier_score));
+        out.push_str(&format!("Accuracy:        {:.4}\n", self.accuracy));
+        out.push_str("\nCalibration (predicted vs. actual win rate per bin):\n");
+        out.push_str(&format!(
+            "{:>11} {:>8} {:>15} {:>16}\n",
+            "bin", "count", "mean predicted", "actual win rate"
+        ));
+        for bin in &self.calibration {
+            out.push_str(&format!(
+                "{:>5.2}-{:<5.2} {:>8} {:>15.4} {:>16.4}\n",
+                bin.bin_start, bin.bin_end, bin.count, bin.mean_predicted, bin.actual_win_rate
+            ));
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_report_matches_known_single_game_metrics() {
+        let report = build_report(&[(0.5, 1.0)], 10);
+
+        assert_eq!(report.games, 1);
+        assert!((report.log_loss - std::f64::consts::LN_2).abs() < 1e-9);
+        assert!((report.brier_score - 0.25).abs() < 1e-9);
+        assert!((report.accuracy - 1.0).abs() < 1e-9);
+
+        let bin = &report.calibration[5]; // p = 0.5 falls in [0.5, 0.6)
+        assert_eq!(bin.count, 1);
+        assert!((bin.mean_predicted - 0.5).abs() < 1e-9);
+        assert!((bin.actual_win_rate - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn build_report_identifies_perfect_calibration() {
+        // Bin covering [0.2, 0.3): predictions at p=0.25, outcomes split so
+        // the actual win rate matches the prediction exactly.
+        let mut games = vec![(0.25, 1.0); 3];
+        games.extend(vec![(0.25, 0.0); 9]);
+        // Bin covering [0.7, 0.8): same idea, mirrored.
+        games.extend(vec![(0.75, 1.0); 9]);
+        games.extend(vec![(0.75, 0.0); 3]);
+
+        let report = build_report(&games, 10);
+
+        let bin_25 = &report.calibration[2];
+        assert_eq!(bin_25.count, 12);
+        assert!((bin_25.mean_predicted - 0.25).abs() < 1e-9);
+        assert!((bin_25.actual_win_rate - 0.25).abs() < 1e-9);
+
+        let bin_75 = &report.calibration[7];
+        assert_eq!(bin_75.count, 12);
+        assert!((bin_75.mean_predicted - 0.75).abs() < 1e-9);
+        assert!((bin_75.actual_win_rate - 0.75).abs() < 1e-9);
+    }
+
+    #[test]
+    fn calibration_bins_clamp_predictions_at_exact_edges() {
+        // p = 0.0 and p = 1.0 are the bin boundaries most likely to trip up
+        // an off-by-one in the bin_idx clamp (1.0 * bins == bins, which
+        // must clamp down into the last bin rather than index out of
+        // range).
+        let report = build_report(&[(0.0, 0.0), (1.0, 1.0)], 10);
+
+        assert_eq!(report.calibration.len(), 10);
+        assert_eq!(report.calibration[0].count, 1);
+        assert_eq!(report.calibration[9].count, 1);
+        assert_eq!(report.calibration.iter().map(|b| b.count).sum::<usize>(), 2);
+    }
+}